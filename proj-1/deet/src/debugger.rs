@@ -1,16 +1,106 @@
-use crate::debugger_command::DebuggerCommand;
-use crate::inferior::{Inferior, Status};
+use crate::debugger_command::{DebuggerCommand, ExecConfig};
+use crate::inferior::{CapturedOutput, Inferior, Status};
+use nix::sys::termios::{self, SetArg, Termios};
 use nix::Error;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 use crate::dwarf_data::{DwarfData,Error as DwarfError};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Puts fd 0 into raw mode for the duration of a PTY-backed run, restoring the original
+/// settings on drop so the `(deet)` prompt gets a sane terminal back.
+struct RawTerminalGuard {
+    original: Option<Termios>,
+}
+
+impl RawTerminalGuard {
+    fn enable() -> Self {
+        let original = termios::tcgetattr(0).ok();
+        if let Some(ref orig) = original {
+            let mut raw = orig.clone();
+            termios::cfmakeraw(&mut raw);
+            let _ = termios::tcsetattr(0, SetArg::TCSANOW, &raw);
+        }
+        RawTerminalGuard { original }
+    }
+}
+
+impl Drop for RawTerminalGuard {
+    fn drop(&mut self) {
+        if let Some(ref orig) = self.original {
+            let _ = termios::tcsetattr(0, SetArg::TCSANOW, orig);
+        }
+    }
+}
+
+/// Spawns the two background threads that shuttle bytes between deet's own stdio and the
+/// inferior's pty master while a `--pty` run is active. Returns the shared flag used to signal
+/// them to stop; they aren't joined since a blocked `stdin.read` could outlive the session.
+fn spawn_pty_pumps(master: std::fs::File) -> Arc<AtomicBool> {
+    let running = Arc::new(AtomicBool::new(true));
+
+    let mut master_out = master.try_clone().expect("failed to clone pty master");
+    let out_flag = running.clone();
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut stdout = std::io::stdout();
+        while out_flag.load(Ordering::SeqCst) {
+            match master_out.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let _ = stdout.write_all(&buf[..n]);
+                    let _ = stdout.flush();
+                }
+            }
+        }
+    });
+
+    let mut master_in = master;
+    let in_flag = running.clone();
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut stdin = std::io::stdin();
+        while in_flag.load(Ordering::SeqCst) {
+            match stdin.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if master_in.write_all(&buf[..n]).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    running
+}
+
+/// Prints the structured summary for a `run --capture`: how the inferior terminated, followed
+/// by everything it wrote to stdout/stderr while it ran.
+fn print_captured_summary(captured: &CapturedOutput) {
+    match &captured.status {
+        Status::Exited(code) => println!("Child exited (status {})", code),
+        Status::Signaled(signal) => println!("Child signaled (signal {})", signal),
+        Status::Stopped(signal, _) => println!("Child stopped (signal {})", signal),
+        Status::None => println!("No running inferior"),
+    }
+    println!("--- stdout ---");
+    let _ = std::io::stdout().write_all(&captured.stdout);
+    println!("--- stderr ---");
+    let _ = std::io::stdout().write_all(&captured.stderr);
+}
 
 pub struct Debugger {
     target: String,
     history_path: String,
     readline: Editor<()>,
     inferior: Option<Inferior>,
-    debug_data:DwarfData
+    debug_data:DwarfData,
+    exec_config: ExecConfig,
 }
 
 impl Debugger {
@@ -40,29 +130,75 @@ impl Debugger {
             readline,
             inferior: None,
             debug_data,
+            exec_config: ExecConfig::default(),
+        }
+    }
+
+    /// Runs the current inferior to its next stop under a pseudo-terminal: puts deet's own
+    /// terminal in raw mode, shuttles bytes to/from the inferior's pty master while it runs, and
+    /// restores the terminal before returning the resulting status.
+    fn run_pty_session(&mut self, timeout: Option<Duration>) -> Result<Status, Error> {
+        let master = self
+            .inferior
+            .as_ref()
+            .and_then(|inferior| inferior.pty_master_clone())
+            .expect("run_pty_session called without a pty-backed inferior");
+
+        let _raw_guard = RawTerminalGuard::enable();
+        let running = spawn_pty_pumps(master);
+        let result = self.inferior.as_mut().unwrap().continue_exec_timeout(timeout);
+        running.store(false, Ordering::SeqCst);
+        result
+    }
+
+    /// Resumes the current inferior, routing through `run_pty_session`'s raw-mode/pump setup
+    /// whenever it's pty-backed so a `continue` after the first stop in a `--pty` session keeps
+    /// shuttling bytes instead of leaving deet's terminal in cooked mode with no active pumps.
+    fn resume_inferior(&mut self, timeout: Option<Duration>) -> Result<Status, Error> {
+        let is_pty = self
+            .inferior
+            .as_ref()
+            .map_or(false, |inferior| inferior.has_pty());
+        if is_pty {
+            self.run_pty_session(timeout)
+        } else {
+            self.inferior.as_mut().unwrap().continue_exec_timeout(timeout)
         }
     }
 
     pub fn run(&mut self) {
         loop {
             let next = match self.get_next_command() {
-                DebuggerCommand::Run(args) => {
+                DebuggerCommand::Run(args, opts) => {
                     if let Some(inferior) = self.inferior.as_mut() {
                         inferior.kill()
-                    } else if let Some(inferior) = Inferior::new(&self.target, &args) {
-                        // Create the inferior
-                        self.inferior = Some(inferior);
-                        // You may use self.inferior.as_mut().unwrap() to get a mutable reference
-                        // to the Inferior object
-                        self.inferior.as_mut().unwrap().continue_exec()
+                    } else if let Some(mut inferior) =
+                        Inferior::new(&self.target, &args, &opts, &self.exec_config)
+                    {
+                        if opts.capture {
+                            // Runs to completion on its own; nothing is left in self.inferior.
+                            match inferior.run_to_completion() {
+                                Ok(captured) => {
+                                    print_captured_summary(&captured);
+                                    Ok(Status::None)
+                                }
+                                Err(_) => Err(Error::Sys(nix::errno::Errno::EIO)),
+                            }
+                        } else {
+                            // Create the inferior
+                            self.inferior = Some(inferior);
+                            // You may use self.inferior.as_mut().unwrap() to get a mutable
+                            // reference to the Inferior object
+                            self.resume_inferior(None)
+                        }
                     } else {
                         Err(Error::Sys(nix::errno::Errno::EIO))
                     }
                 }
 
-                DebuggerCommand::Continue => {
-                    if let Some(inferior) = self.inferior.as_mut() {
-                        inferior.continue_exec()
+                DebuggerCommand::Continue(timeout_secs) => {
+                    if self.inferior.is_some() {
+                        self.resume_inferior(timeout_secs.map(Duration::from_secs))
                     } else {
                         Err(Error::Sys(nix::errno::Errno::EIO))
                     }
@@ -83,6 +219,27 @@ impl Debugger {
                         Err(Error::Sys(nix::errno::Errno::EIO))
                     }
                 }
+
+                DebuggerCommand::SetEnv(key, value) => {
+                    println!("Will set {}={} for the next run", key, value);
+                    self.exec_config.set_env(key, value);
+                    Ok(Status::None)
+                }
+                DebuggerCommand::UnsetEnv(key) => {
+                    println!("Will unset {} for the next run", key);
+                    self.exec_config.unset_env(key);
+                    Ok(Status::None)
+                }
+                DebuggerCommand::SetCwd(path) => {
+                    println!("Will use {} as the working directory for the next run", path);
+                    self.exec_config.cwd = Some(path);
+                    Ok(Status::None)
+                }
+                DebuggerCommand::SetLimit(limit, value) => {
+                    println!("Will set {:?} limit to {} for the next run", limit, value);
+                    self.exec_config.set_limit(limit, value);
+                    Ok(Status::None)
+                }
             };
 
             match next {
@@ -0,0 +1,394 @@
+use nix::sys::resource::Resource;
+use std::io;
+use std::process::Stdio;
+
+/// Describes how one of the inferior's standard streams should be connected when it is spawned.
+/// Mirrors the `Stdio` cases `std::process::Command` already understands (`Inherit` / `Piped` /
+/// `Null`), plus the file-redirection forms the `run` command accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamRedirect {
+    /// Share deet's own stdio. This is the default.
+    Inherit,
+    /// Discard the stream.
+    Null,
+    /// Connect a pipe whose handle the debugger keeps around.
+    Piped,
+    /// Redirect to/from a file, truncating it first.
+    File(String),
+    /// Redirect to a file, appending instead of truncating.
+    AppendFile(String),
+}
+
+impl Default for StreamRedirect {
+    fn default() -> Self {
+        StreamRedirect::Inherit
+    }
+}
+
+impl StreamRedirect {
+    fn to_stdin_stdio(&self) -> io::Result<Stdio> {
+        Ok(match self {
+            StreamRedirect::Inherit => Stdio::inherit(),
+            StreamRedirect::Null => Stdio::null(),
+            StreamRedirect::Piped => Stdio::piped(),
+            StreamRedirect::File(path) => Stdio::from(std::fs::File::open(path)?),
+            StreamRedirect::AppendFile(path) => Stdio::from(std::fs::File::open(path)?),
+        })
+    }
+
+    fn to_output_stdio(&self) -> io::Result<Stdio> {
+        Ok(match self {
+            StreamRedirect::Inherit => Stdio::inherit(),
+            StreamRedirect::Null => Stdio::null(),
+            StreamRedirect::Piped => Stdio::piped(),
+            StreamRedirect::File(path) => Stdio::from(std::fs::File::create(path)?),
+            StreamRedirect::AppendFile(path) => Stdio::from(
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?,
+            ),
+        })
+    }
+}
+
+/// Stdio redirection requested for a `run` invocation, parsed from shell-style tokens
+/// (`< input.txt`, `> out.log`, `2> err.log`, `2>&1`, ...).
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    pub stdin: StreamRedirect,
+    pub stdout: StreamRedirect,
+    pub stderr: StreamRedirect,
+    /// Whether `2>&1` was given: stderr should land wherever stdout lands.
+    pub stderr_to_stdout: bool,
+    /// Whether `--pty` was given: give the inferior its own controlling terminal instead of
+    /// deet's, so `isatty`/raw-mode/ANSI-emitting programs behave the way they would standalone.
+    pub pty: bool,
+    /// Whether `--capture` was given: run the inferior to completion, piping and buffering its
+    /// stdout/stderr instead of handing control back to the `(deet)` prompt after each stop.
+    pub capture: bool,
+}
+
+impl RunOptions {
+    /// Resolves this into the `(stdin, stdout, stderr)` trio to hand to `Command`.
+    ///
+    /// `std::process::Command` has no way to alias one `Stdio` to another the way a shell's
+    /// `2>&1` does, so when stdout is file-backed we approximate it by reopening the same file
+    /// for stderr in append mode; when stdout is piped we fall back to giving stderr its own pipe.
+    ///
+    /// `--pty` takes precedence over any file/pipe redirection: `Inferior::new` dup2's the pty
+    /// slave onto the child's stdin/stdout/stderr after `Command` applies whatever this returns,
+    /// so honoring redirection here would just open files or pipes that get silently clobbered.
+    /// Returning inherited stdio instead makes that precedence explicit rather than an accidental
+    /// last-writer-wins.
+    pub fn build_stdio(&self) -> io::Result<(Stdio, Stdio, Stdio)> {
+        if self.pty {
+            return Ok((Stdio::inherit(), Stdio::inherit(), Stdio::inherit()));
+        }
+        let stdin = self.stdin.to_stdin_stdio()?;
+        let stdout = self.stdout.to_output_stdio()?;
+        let stderr = if self.stderr_to_stdout {
+            match &self.stdout {
+                StreamRedirect::File(path) | StreamRedirect::AppendFile(path) => Stdio::from(
+                    std::fs::OpenOptions::new().append(true).open(path)?,
+                ),
+                StreamRedirect::Piped => Stdio::piped(),
+                _ => self.stderr.to_output_stdio()?,
+            }
+        } else {
+            self.stderr.to_output_stdio()?
+        };
+        Ok((stdin, stdout, stderr))
+    }
+}
+
+/// A POSIX resource limit that can be applied to the inferior before it execs, named the way
+/// users type it at the `(deet)` prompt (`set limit cpu 5`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    Cpu,
+    AddressSpace,
+    Core,
+    NoFile,
+}
+
+impl ResourceLimit {
+    fn from_name(name: &str) -> Option<ResourceLimit> {
+        match name {
+            "cpu" => Some(ResourceLimit::Cpu),
+            "as" | "mem" | "memory" => Some(ResourceLimit::AddressSpace),
+            "core" => Some(ResourceLimit::Core),
+            "nofile" | "fds" => Some(ResourceLimit::NoFile),
+            _ => None,
+        }
+    }
+
+    pub fn resource(&self) -> Resource {
+        match self {
+            ResourceLimit::Cpu => Resource::RLIMIT_CPU,
+            ResourceLimit::AddressSpace => Resource::RLIMIT_AS,
+            ResourceLimit::Core => Resource::RLIMIT_CORE,
+            ResourceLimit::NoFile => Resource::RLIMIT_NOFILE,
+        }
+    }
+}
+
+/// The execution environment the next `run` should use: extra/overridden env vars, vars to
+/// unset, a working directory, and rlimits. Persists across `run` commands until changed again.
+#[derive(Debug, Clone, Default)]
+pub struct ExecConfig {
+    pub env: Vec<(String, String)>,
+    pub env_remove: Vec<String>,
+    pub cwd: Option<String>,
+    pub limits: Vec<(ResourceLimit, u64)>,
+}
+
+impl ExecConfig {
+    pub fn set_env(&mut self, key: String, value: String) {
+        self.env_remove.retain(|k| k != &key);
+        self.env.retain(|(k, _)| k != &key);
+        self.env.push((key, value));
+    }
+
+    pub fn unset_env(&mut self, key: String) {
+        self.env.retain(|(k, _)| k != &key);
+        self.env_remove.push(key);
+    }
+
+    pub fn set_limit(&mut self, limit: ResourceLimit, value: u64) {
+        self.limits.retain(|(l, _)| *l != limit);
+        self.limits.push((limit, value));
+    }
+}
+
+pub enum DebuggerCommand {
+    Quit,
+    Run(Vec<String>, RunOptions),
+    /// `continue`, optionally `continue <seconds>` to re-stop the inferior after a timeout.
+    Continue(Option<u64>),
+    Backtrace,
+    SetEnv(String, String),
+    UnsetEnv(String),
+    SetCwd(String),
+    SetLimit(ResourceLimit, u64),
+}
+
+/// Splits a `run` command's tokens into the program arguments and any redirection it requested.
+fn parse_run_tokens(tokens: &[&str]) -> (Vec<String>, RunOptions) {
+    let mut args = Vec::new();
+    let mut opts = RunOptions::default();
+    let mut iter = tokens.iter();
+    while let Some(&tok) = iter.next() {
+        match tok {
+            "<" => {
+                if let Some(&path) = iter.next() {
+                    opts.stdin = StreamRedirect::File(path.to_string());
+                }
+            }
+            ">" | "1>" => {
+                if let Some(&path) = iter.next() {
+                    opts.stdout = StreamRedirect::File(path.to_string());
+                }
+            }
+            ">>" | "1>>" => {
+                if let Some(&path) = iter.next() {
+                    opts.stdout = StreamRedirect::AppendFile(path.to_string());
+                }
+            }
+            "2>" => {
+                if let Some(&path) = iter.next() {
+                    opts.stderr = StreamRedirect::File(path.to_string());
+                }
+            }
+            "2>>" => {
+                if let Some(&path) = iter.next() {
+                    opts.stderr = StreamRedirect::AppendFile(path.to_string());
+                }
+            }
+            "2>&1" => {
+                opts.stderr_to_stdout = true;
+            }
+            "--pty" => {
+                opts.pty = true;
+            }
+            "--capture" => {
+                opts.capture = true;
+            }
+            _ => args.push(tok.to_string()),
+        }
+    }
+    if opts.capture {
+        if opts.stdout == StreamRedirect::Inherit {
+            opts.stdout = StreamRedirect::Piped;
+        }
+        if opts.stderr == StreamRedirect::Inherit {
+            opts.stderr = StreamRedirect::Piped;
+        }
+    }
+    (args, opts)
+}
+
+impl DebuggerCommand {
+    pub fn from_tokens(tokens: &Vec<&str>) -> Option<DebuggerCommand> {
+        match tokens[0] {
+            "q" | "quit" => Some(DebuggerCommand::Quit),
+            "r" | "run" => {
+                let (args, opts) = parse_run_tokens(&tokens[1..]);
+                Some(DebuggerCommand::Run(args, opts))
+            }
+            "c" | "cont" | "continue" => {
+                let timeout_secs = tokens.get(1).copied().and_then(|s| s.parse::<u64>().ok());
+                Some(DebuggerCommand::Continue(timeout_secs))
+            }
+            "bt" | "back" | "backtrace" => Some(DebuggerCommand::Backtrace),
+            "set" => match tokens.get(1).copied() {
+                Some("env") => {
+                    let assignment = tokens.get(2).copied()?;
+                    let mut parts = assignment.splitn(2, '=');
+                    let key = parts.next()?.to_string();
+                    let value = parts.next().unwrap_or("").to_string();
+                    Some(DebuggerCommand::SetEnv(key, value))
+                }
+                Some("cwd") => {
+                    let path = tokens.get(2).copied()?;
+                    Some(DebuggerCommand::SetCwd(path.to_string()))
+                }
+                Some("limit") => {
+                    let name = tokens.get(2).copied()?;
+                    let value = tokens.get(3).copied()?.parse::<u64>().ok()?;
+                    let limit = ResourceLimit::from_name(name)?;
+                    Some(DebuggerCommand::SetLimit(limit, value))
+                }
+                _ => None,
+            },
+            "unset" => match tokens.get(1).copied() {
+                Some("env") => {
+                    let key = tokens.get(2).copied()?;
+                    Some(DebuggerCommand::UnsetEnv(key.to_string()))
+                }
+                _ => None,
+            },
+            // Default case:
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_run_tokens_with_no_redirection() {
+        let (args, opts) = parse_run_tokens(&["a.out", "arg1", "arg2"]);
+        assert_eq!(args, vec!["a.out", "arg1", "arg2"]);
+        assert_eq!(opts.stdin, StreamRedirect::Inherit);
+        assert_eq!(opts.stdout, StreamRedirect::Inherit);
+        assert_eq!(opts.stderr, StreamRedirect::Inherit);
+        assert!(!opts.stderr_to_stdout);
+        assert!(!opts.pty);
+        assert!(!opts.capture);
+    }
+
+    #[test]
+    fn parse_run_tokens_with_file_redirection() {
+        let (args, opts) =
+            parse_run_tokens(&["a.out", "<", "in.txt", ">", "out.txt", "2>>", "err.txt"]);
+        assert_eq!(args, vec!["a.out"]);
+        assert_eq!(opts.stdin, StreamRedirect::File("in.txt".to_string()));
+        assert_eq!(opts.stdout, StreamRedirect::File("out.txt".to_string()));
+        assert_eq!(opts.stderr, StreamRedirect::AppendFile("err.txt".to_string()));
+    }
+
+    #[test]
+    fn parse_run_tokens_with_stderr_to_stdout() {
+        let (_, opts) = parse_run_tokens(&["a.out", "2>&1"]);
+        assert!(opts.stderr_to_stdout);
+    }
+
+    #[test]
+    fn parse_run_tokens_capture_pipes_stdout_and_stderr_by_default() {
+        let (_, opts) = parse_run_tokens(&["a.out", "--capture"]);
+        assert!(opts.capture);
+        assert_eq!(opts.stdout, StreamRedirect::Piped);
+        assert_eq!(opts.stderr, StreamRedirect::Piped);
+    }
+
+    #[test]
+    fn parse_run_tokens_capture_does_not_override_explicit_redirection() {
+        let (_, opts) = parse_run_tokens(&["a.out", ">", "out.txt", "--capture"]);
+        assert_eq!(opts.stdout, StreamRedirect::File("out.txt".to_string()));
+        assert_eq!(opts.stderr, StreamRedirect::Piped);
+    }
+
+    #[test]
+    fn build_stdio_aliases_stderr_to_a_file_backed_stdout() {
+        let dir = std::env::temp_dir().join("deet_test_build_stdio_alias");
+        let path = dir.to_str().unwrap().to_string();
+        let opts = RunOptions {
+            stdout: StreamRedirect::File(path),
+            stderr_to_stdout: true,
+            ..RunOptions::default()
+        };
+        assert!(opts.build_stdio().is_ok());
+        let _ = std::fs::remove_file(&dir);
+    }
+
+    #[test]
+    fn build_stdio_gives_stderr_its_own_pipe_when_stdout_is_piped() {
+        let opts = RunOptions {
+            stdout: StreamRedirect::Piped,
+            stderr_to_stdout: true,
+            ..RunOptions::default()
+        };
+        assert!(opts.build_stdio().is_ok());
+    }
+
+    #[test]
+    fn build_stdio_ignores_explicit_redirection_when_pty_is_set() {
+        // A nonexistent directory, so if `--pty` didn't short-circuit the redirection below,
+        // opening this path would fail and `build_stdio` would return an `Err`.
+        let opts = RunOptions {
+            stdin: StreamRedirect::File("/no/such/directory/in.txt".to_string()),
+            pty: true,
+            ..RunOptions::default()
+        };
+        assert!(opts.build_stdio().is_ok());
+    }
+
+    #[test]
+    fn resource_limit_from_name_recognizes_known_names() {
+        assert_eq!(ResourceLimit::from_name("cpu"), Some(ResourceLimit::Cpu));
+        assert_eq!(ResourceLimit::from_name("as"), Some(ResourceLimit::AddressSpace));
+        assert_eq!(ResourceLimit::from_name("mem"), Some(ResourceLimit::AddressSpace));
+        assert_eq!(ResourceLimit::from_name("core"), Some(ResourceLimit::Core));
+        assert_eq!(ResourceLimit::from_name("nofile"), Some(ResourceLimit::NoFile));
+        assert_eq!(ResourceLimit::from_name("bogus"), None);
+    }
+
+    #[test]
+    fn exec_config_set_env_overwrites_and_cancels_a_pending_unset() {
+        let mut config = ExecConfig::default();
+        config.unset_env("FOO".to_string());
+        config.set_env("FOO".to_string(), "bar".to_string());
+        assert_eq!(config.env, vec![("FOO".to_string(), "bar".to_string())]);
+        assert!(config.env_remove.is_empty());
+    }
+
+    #[test]
+    fn exec_config_unset_env_removes_any_pending_set() {
+        let mut config = ExecConfig::default();
+        config.set_env("FOO".to_string(), "bar".to_string());
+        config.unset_env("FOO".to_string());
+        assert!(config.env.is_empty());
+        assert_eq!(config.env_remove, vec!["FOO".to_string()]);
+    }
+
+    #[test]
+    fn exec_config_set_limit_replaces_an_existing_value() {
+        let mut config = ExecConfig::default();
+        config.set_limit(ResourceLimit::Cpu, 5);
+        config.set_limit(ResourceLimit::Cpu, 10);
+        assert_eq!(config.limits, vec![(ResourceLimit::Cpu, 10)]);
+    }
+}
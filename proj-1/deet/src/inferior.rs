@@ -1,11 +1,64 @@
 use nix::sys::ptrace;
-use nix::sys::signal::{Signal, SIGTRAP};
+use nix::sys::resource::setrlimit;
+use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal, SIGTRAP};
 use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
-use nix::unistd::Pid;
+use nix::unistd::{close, dup2, setpgid, setsid, Pid};
+use nix::pty::openpty;
 use nix::Error;
+use std::io::Read;
+use std::os::unix::io::{FromRawFd, RawFd};
 use std::os::unix::process::CommandExt;
-use std::process::{Child, Command};
+use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use crate::dwarf_data::DwarfData;
+use crate::debugger_command::{ExecConfig, ResourceLimit, RunOptions};
+
+/// Set by `sigint_handler` when deet itself receives SIGINT while polling a `continue`. Plain
+/// signal-handler state has to be a static, since the handler can't capture anything; it's only
+/// ever read/cleared from `resume`'s poll loop.
+static CONTINUE_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn sigint_handler(_: nix::libc::c_int) {
+    CONTINUE_INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Installs a SIGINT handler that just raises `CONTINUE_INTERRUPTED`, for the duration of a
+/// `continue` poll loop, and restores whatever disposition (readline's own handler, or anything
+/// else) was in place before on drop — the same save/restore shape `RawTerminalGuard` in
+/// debugger.rs uses for termios, rather than assuming what SIGINT was set to beforehand.
+struct SigintGuard {
+    previous: SigAction,
+}
+
+impl SigintGuard {
+    fn install() -> Result<Self, Error> {
+        let action = SigAction::new(
+            SigHandler::Handler(sigint_handler),
+            SaFlags::empty(),
+            SigSet::empty(),
+        );
+        let previous = unsafe { signal::sigaction(Signal::SIGINT, &action)? };
+        Ok(SigintGuard { previous })
+    }
+}
+
+impl Drop for SigintGuard {
+    fn drop(&mut self) {
+        let _ = unsafe { signal::sigaction(Signal::SIGINT, &self.previous) };
+    }
+}
+
+/// Puts the child in its own process group so that forwarding SIGINT/SIGSTOP to "the inferior"
+/// can target its group without also hitting deet. Only needed when `--pty` isn't in play:
+/// `child_set_controlling_pty`'s `setsid()` already makes the child a new session and process
+/// group leader, and `setsid()` fails with `EPERM` if the caller is already a group leader, so
+/// calling both would always break `run --pty`.
+fn child_new_process_group() -> Result<(), std::io::Error> {
+    setpgid(Pid::from_raw(0), Pid::from_raw(0))
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "setpgid failed"))
+}
 
 pub enum Status {
     /// Indicates inferior stopped. Contains the signal that stopped the process, as well as the
@@ -22,6 +75,14 @@ pub enum Status {
     None,
 }
 
+/// The result of `Inferior::run_to_completion`: how the inferior ended, plus everything it wrote
+/// to stdout/stderr while it ran.
+pub struct CapturedOutput {
+    pub status: Status,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
 /// This function calls ptrace with PTRACE_TRACEME to enable debugging on a process. You should use
 /// pre_exec with Command to call this in the child process.
 fn child_traceme() -> Result<(), std::io::Error> {
@@ -29,22 +90,108 @@ fn child_traceme() -> Result<(), std::io::Error> {
         .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "ptrace TRACEME failed"))
 }
 
+/// Makes the child its own session leader and installs `slave` as its controlling terminal,
+/// dup'ing it onto stdin/stdout/stderr. Runs in the child between `fork` and `exec`, alongside
+/// `child_traceme`, so the inferior gets a real tty instead of sharing deet's.
+fn child_set_controlling_pty(slave: RawFd) -> Result<(), std::io::Error> {
+    setsid().map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "setsid failed"))?;
+    if unsafe { nix::libc::ioctl(slave, nix::libc::TIOCSCTTY as _, 0) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    for fd in 0..=2 {
+        dup2(slave, fd)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "dup2 onto pty failed"))?;
+    }
+    if slave > 2 {
+        let _ = close(slave);
+    }
+    Ok(())
+}
+
+/// Applies the configured rlimits to the child, after `child_traceme` but before `exec`, so
+/// users can reproduce crashes that only show up under constrained memory/CPU/fds.
+fn child_apply_rlimits(limits: &[(ResourceLimit, u64)]) -> Result<(), std::io::Error> {
+    for (limit, value) in limits {
+        setrlimit(limit.resource(), *value, *value)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::Other, "setrlimit failed"))?;
+    }
+    Ok(())
+}
+
 pub struct Inferior {
     child: Child,
+    /// Write end of the inferior's stdin, kept around when `run` requested a pipe so a later
+    /// milestone can feed it input programmatically.
+    stdin: Option<ChildStdin>,
+    /// Read end of the inferior's stdout, kept around for the same reason.
+    stdout: Option<ChildStdout>,
+    /// Read end of the inferior's stderr, kept around for the same reason.
+    stderr: Option<ChildStderr>,
+    /// Master side of the inferior's pseudo-terminal, present when `run --pty` was used.
+    pty_master: Option<std::fs::File>,
 }
 
 ///  An inferior is a process that is being traced by the debugger.
 impl Inferior {
     /// Attempts to start a new inferior process. Returns Some(Inferior) if successful, or None if
-    /// an error is encountered.
-    pub fn new(target: &str, args: &Vec<String>) -> Option<Inferior> {
+    /// an error is encountered. `opts` controls how the inferior's stdin/stdout/stderr are wired
+    /// up (inherited, redirected to a file, or piped back to the debugger).
+    pub fn new(
+        target: &str,
+        args: &Vec<String>,
+        opts: &RunOptions,
+        exec_config: &ExecConfig,
+    ) -> Option<Inferior> {
+        let (stdin, stdout, stderr) = opts.build_stdio().ok()?;
+        let pty = if opts.pty {
+            // Propagate failure instead of silently falling back to non-pty stdio: callers
+            // (Debugger::run_pty_session) assume a pty-backed Inferior actually has one.
+            Some(openpty(None, None).ok()?)
+        } else {
+            None
+        };
+
+        let mut command = Command::new(target);
+        command.args(args).stdin(stdin).stdout(stdout).stderr(stderr);
+        for (key, value) in &exec_config.env {
+            command.env(key, value);
+        }
+        for key in &exec_config.env_remove {
+            command.env_remove(key);
+        }
+        if let Some(cwd) = &exec_config.cwd {
+            command.current_dir(cwd);
+        }
+        let limits = exec_config.limits.clone();
         unsafe {
-            Command::new(target)
-                .args(args)
-                .pre_exec(child_traceme)
+            command.pre_exec(child_traceme);
+            if pty.is_none() {
+                command.pre_exec(child_new_process_group);
+            }
+            if !limits.is_empty() {
+                command.pre_exec(move || child_apply_rlimits(&limits));
+            }
+            if let Some(ref pty) = pty {
+                let slave = pty.slave;
+                command.pre_exec(move || child_set_controlling_pty(slave));
+            }
+            command
                 .spawn()
-                .map(|child| {
-                    let inferior = Inferior { child };
+                .map(|mut child| {
+                    let stdin = child.stdin.take();
+                    let stdout = child.stdout.take();
+                    let stderr = child.stderr.take();
+                    if let Some(pty) = &pty {
+                        let _ = close(pty.slave);
+                    }
+                    let pty_master = pty.map(|pty| unsafe { std::fs::File::from_raw_fd(pty.master) });
+                    let inferior = Inferior {
+                        child,
+                        stdin,
+                        stdout,
+                        stderr,
+                        pty_master,
+                    };
 
                     if let Status::Stopped(SIGTRAP, _) =
                         inferior.wait(Some(WaitPidFlag::WSTOPPED)).unwrap()
@@ -58,13 +205,130 @@ impl Inferior {
         }
     }
 
+    /// Returns a cloned handle to the pty master, if this inferior was spawned with `--pty`.
+    /// Cloned so the caller can hand ownership to a reader/writer thread while `Inferior` keeps
+    /// its own copy alive for the lifetime of the session.
+    pub fn pty_master_clone(&self) -> Option<std::fs::File> {
+        self.pty_master.as_ref().and_then(|f| f.try_clone().ok())
+    }
+
+    /// Returns whether this inferior was spawned with `--pty`, i.e. whether it has its own
+    /// controlling terminal rather than deet's stdio.
+    pub fn has_pty(&self) -> bool {
+        self.pty_master.is_some()
+    }
+
+    /// Returns a mutable handle to the inferior's stdin, if `run` piped it (see `RunOptions`), so
+    /// a caller can feed the inferior input programmatically instead of leaving the pipe unused.
+    pub fn stdin_mut(&mut self) -> Option<&mut ChildStdin> {
+        self.stdin.as_mut()
+    }
+
     /// resume the inferior from initial SIGTRAP
     pub fn continue_exec(&mut self) -> Result<Status, Error> {
+        self.continue_exec_timeout(None)
+    }
+
+    /// Resumes the inferior and polls for it to stop instead of blocking forever in `waitpid`, so
+    /// a hung or spinning inferior doesn't wedge deet. If `timeout` elapses, or the user sends
+    /// SIGINT while we're polling, the inferior's process group is signaled and re-stopped so
+    /// control returns to the `(deet)` prompt either way.
+    pub fn continue_exec_timeout(&mut self, timeout: Option<Duration>) -> Result<Status, Error> {
+        self.resume(None, timeout)
+    }
+
+    /// Core of `continue_exec`/`continue_exec_timeout`. `signal`, if given, is re-delivered to the
+    /// tracee on this `ptrace::cont` instead of being swallowed — needed by `run_to_completion` to
+    /// forward a signal that caused the last stop, since otherwise the kernel never actually acts
+    /// on it (e.g. a real SIGSEGV would just re-fault the same instruction forever instead of
+    /// terminating the inferior).
+    fn resume(&mut self, signal: Option<Signal>, timeout: Option<Duration>) -> Result<Status, Error> {
         if !self.check_running() {
             return Ok(Status::None);
         }
-        ptrace::cont(self.pid(), None)?;
-        self.wait(None)
+        let _sigint_guard = SigintGuard::install()?;
+        CONTINUE_INTERRUPTED.store(false, Ordering::SeqCst);
+        ptrace::cont(self.pid(), signal)?;
+
+        let poll_interval = Duration::from_millis(20);
+        let started = Instant::now();
+        loop {
+            match waitpid(self.pid(), Some(WaitPidFlag::WNOHANG))? {
+                WaitStatus::StillAlive => {
+                    if CONTINUE_INTERRUPTED.swap(false, Ordering::SeqCst) {
+                        println!("Interrupted by user, stopping inferior");
+                        let _ = signal::killpg(self.pid(), Signal::SIGSTOP);
+                        return self.wait(None);
+                    }
+                    if let Some(timeout) = timeout {
+                        if started.elapsed() >= timeout {
+                            println!("Continue timed out after {:?}, stopping inferior", timeout);
+                            let _ = signal::killpg(self.pid(), Signal::SIGSTOP);
+                            return self.wait(None);
+                        }
+                    }
+                    thread::sleep(poll_interval);
+                }
+                status => return self.status_from_wait(status),
+            }
+        }
+    }
+
+    /// Runs the inferior to completion (`run --capture`), draining its piped stdout/stderr on
+    /// background threads as it goes so a full pipe buffer can't deadlock the debugger. Loops,
+    /// resuming on a `Stopped` status (e.g. a delivered signal other than the one that ends the
+    /// process) rather than ending the capture — except a `SIGSTOP`, which is how
+    /// `continue_exec_timeout` reports a user interrupt or timeout; resuming past that would make
+    /// Ctrl-C a silent no-op during a capture, so that case aborts the run instead. Every other
+    /// stop's signal is re-delivered on the next resume so the inferior actually sees it (e.g. a
+    /// real SIGSEGV needs to be redelivered to terminate; swallowing it would just re-fault the
+    /// same instruction forever).
+    pub fn run_to_completion(&mut self) -> Result<CapturedOutput, Error> {
+        let stdout_reader = self.stdout.take().map(|mut out| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = out.read_to_end(&mut buf);
+                buf
+            })
+        });
+        let stderr_reader = self.stderr.take().map(|mut err| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                let _ = err.read_to_end(&mut buf);
+                buf
+            })
+        });
+
+        let mut pending_signal = None;
+        let status = loop {
+            match self.resume(pending_signal.take(), None)? {
+                stopped @ Status::Stopped(Signal::SIGSTOP, _) => {
+                    // Kill rather than leave it merely stopped: the readers below block on
+                    // `read_to_end` until they see EOF, which only happens once the inferior's
+                    // end of the pipe closes.
+                    let _ = self.kill();
+                    break stopped;
+                }
+                Status::Stopped(signal, _) => {
+                    pending_signal = Some(signal);
+                    continue;
+                }
+                terminal => break terminal,
+            }
+        };
+
+        let stdout = stdout_reader
+            .map(|handle| handle.join().unwrap_or_default())
+            .unwrap_or_default();
+        let stderr = stderr_reader
+            .map(|handle| handle.join().unwrap_or_default())
+            .unwrap_or_default();
+
+        Ok(CapturedOutput {
+            status,
+            stdout,
+            stderr,
+        })
     }
 
     pub fn kill(&mut self) -> Result<Status, Error> {
@@ -131,7 +395,14 @@ impl Inferior {
     /// Calls waitpid on this inferior and returns a Status to indicate the state of the process
     /// after the waitpid call.
     pub fn wait(&self, options: Option<WaitPidFlag>) -> Result<Status, Error> {
-        Ok(match waitpid(self.pid(), options)? {
+        let status = waitpid(self.pid(), options)?;
+        self.status_from_wait(status)
+    }
+
+    /// Converts an already-retrieved `WaitStatus` into our `Status`, without calling `waitpid`
+    /// itself. Shared by `wait` and `resume`'s `WNOHANG` poll loop.
+    fn status_from_wait(&self, status: WaitStatus) -> Result<Status, Error> {
+        Ok(match status {
             WaitStatus::Exited(_pid, exit_code) => Status::Exited(exit_code),
             WaitStatus::Signaled(_pid, signal, _core_dumped) => Status::Signaled(signal),
             WaitStatus::Stopped(_pid, signal) => {